@@ -0,0 +1,62 @@
+use crate::error::CollectError;
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Writes records for a single collection run into
+/// `<root>/<source>/<unix-timestamp>/`, so each run leaves an immutable,
+/// point-in-time snapshot instead of an ever-growing flat blob. One
+/// `Archive` should be constructed per run and reused for every record
+/// that run produces, rather than one per record — each `write` call
+/// gets its own file within the shared run directory. Runs of the same
+/// source that start within the same second share one directory.
+pub struct Archive {
+    dir: PathBuf,
+    next_index: AtomicU32,
+}
+
+impl Archive {
+    /// Starts a new run for `source`, stamping it with the current UNIX
+    /// time (in seconds, per the `data/<source>/<unix-timestamp>/` layout)
+    /// once so every record written through this `Archive` lands in the
+    /// same run directory.
+    pub fn new(root: impl AsRef<Path>, source: &str) -> Result<Self, CollectError> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let dir = root.as_ref().join(source).join(timestamp.to_string());
+        fs::create_dir_all(&dir)?;
+        Ok(Archive {
+            dir,
+            next_index: AtomicU32::new(0),
+        })
+    }
+
+    /// Serializes `record` into its own file inside this run's directory.
+    /// The first record in a run is `record.json`; later ones in the same
+    /// run are `record-1.json`, `record-2.json`, and so on. A file that
+    /// already exists in the directory is never overwritten; the next free
+    /// name is used instead.
+    pub fn write<R: Serialize>(&self, record: &R) -> Result<(), CollectError> {
+        let body = serde_json::to_string_pretty(record)?;
+        loop {
+            let index = self.next_index.fetch_add(1, Ordering::SeqCst);
+            let name = match index {
+                0 => "record.json".to_string(),
+                n => format!("record-{}.json", n),
+            };
+            let path = self.dir.join(name);
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(body.as_bytes())?;
+                    return Ok(());
+                }
+                // A name already taken by an earlier run that started in
+                // the same second: move on to the next one.
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use crate::error::CollectError;
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+
+/// Implemented by record types that can be flattened into CSV columns,
+/// with nested structs (e.g. `Status`) expanded into dotted column names
+/// such as `status.verified`.
+pub trait CsvRow {
+    fn headers() -> Vec<&'static str>;
+    fn row(&self) -> Vec<String>;
+}
+
+/// Appends rows to `<root>/<source>.csv`, writing the header only the
+/// first time the file is created.
+pub struct CsvSink {
+    path: PathBuf,
+}
+
+impl CsvSink {
+    pub fn new(root: impl AsRef<Path>, source: &str) -> Result<Self, CollectError> {
+        std::fs::create_dir_all(root.as_ref())?;
+        Ok(CsvSink {
+            path: root.as_ref().join(format!("{}.csv", source)),
+        })
+    }
+
+    /// Appends `record` as a row, writing the header row first if the
+    /// file didn't already exist.
+    pub fn append<R: CsvRow>(&self, record: &R) -> Result<(), CollectError> {
+        let is_new = !self.path.exists();
+        let file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(file);
+        if is_new {
+            writer.write_record(R::headers())?;
+        }
+        writer.write_record(record.row())?;
+        writer.flush()?;
+        Ok(())
+    }
+}
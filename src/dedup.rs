@@ -0,0 +1,93 @@
+use crate::error::CollectError;
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Implemented by record types that carry a stable logical identity (e.g.
+/// the API's own `_id`), so repeated harvests of the same underlying
+/// record can be recognized as duplicates rather than stored again.
+pub trait Identity {
+    fn identity(&self) -> String;
+}
+
+/// Tracks which record identities have already been seen for one source,
+/// backed by a flat append-only file so the index survives across runs.
+pub struct SeenIndex {
+    path: PathBuf,
+    seen: HashSet<String>,
+}
+
+impl SeenIndex {
+    /// Loads the identities already recorded for `source` under `root`,
+    /// creating an empty index if none exists yet.
+    pub fn load(root: impl AsRef<Path>, source: &str) -> Result<Self, CollectError> {
+        fs::create_dir_all(root.as_ref())?;
+        let path = root.as_ref().join(format!("{}.seen", source));
+        let seen = if path.exists() {
+            BufReader::new(fs::File::open(&path)?)
+                .lines()
+                .collect::<std::io::Result<_>>()?
+        } else {
+            HashSet::new()
+        };
+        Ok(SeenIndex { path, seen })
+    }
+
+    /// Returns whether `id` has already been recorded as seen.
+    pub fn contains(&self, id: &str) -> bool {
+        self.seen.contains(id)
+    }
+
+    /// Records `id` as seen. Callers must check `contains` first (and
+    /// persist the record) before calling this, so a record is never
+    /// marked seen unless it actually made it to disk.
+    pub fn insert(&mut self, id: &str) -> Result<(), CollectError> {
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", id)?;
+        self.seen.insert(id.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "playground-data-collection-rust-test-dedup-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn unseen_identity_is_not_contained() {
+        let root = temp_root("unseen");
+        let index = SeenIndex::load(&root, "cat-fact").unwrap();
+        assert!(!index.contains("abc"));
+    }
+
+    #[test]
+    fn inserted_identity_is_contained() {
+        let root = temp_root("inserted");
+        let mut index = SeenIndex::load(&root, "cat-fact").unwrap();
+        index.insert("abc").unwrap();
+        assert!(index.contains("abc"));
+    }
+
+    #[test]
+    fn index_survives_reload() {
+        let root = temp_root("reload");
+        {
+            let mut index = SeenIndex::load(&root, "cat-fact").unwrap();
+            index.insert("abc").unwrap();
+        }
+        let index = SeenIndex::load(&root, "cat-fact").unwrap();
+        assert!(index.contains("abc"));
+        assert!(!index.contains("xyz"));
+    }
+}
@@ -0,0 +1,27 @@
+use thiserror::Error;
+
+/// Errors that can occur while harvesting, parsing, and persisting a
+/// single record. Kept distinct from a catch-all `anyhow::Error` so
+/// callers can match on e.g. `HttpStatus` to decide whether a failure is
+/// worth retrying, and so I/O failures are reported precisely instead of
+/// being relabeled as something else.
+#[derive(Debug, Error)]
+pub enum CollectError {
+    #[error("request failed: {0}")]
+    Request(#[from] reqwest::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("server responded with {0}")]
+    HttpStatus(reqwest::StatusCode),
+
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("system clock error: {0}")]
+    Clock(#[from] std::time::SystemTimeError),
+
+    #[error("csv error: {0}")]
+    Csv(#[from] csv::Error),
+}
@@ -1,33 +1,496 @@
 #[macro_use]
 extern crate log;
 
-use anyhow::anyhow;
-use jfs::Store;
+mod archive;
+mod csv_export;
+mod dedup;
+mod error;
+
+use anyhow::Result;
+use archive::Archive;
+use csv_export::{CsvRow, CsvSink};
+use dedup::{Identity, SeenIndex};
+use error::CollectError;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Looks up `--flag value` in argv, returning the value that follows it.
+fn flag_value(args: &[String], flag: &str) -> Option<String> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
+
+/// Starting backoff before the first retry.
+const RETRY_BASE: Duration = Duration::from_millis(200);
+/// Backoff is capped here so a flaky run doesn't end up sleeping for ages
+/// between attempts.
+const RETRY_MAX: Duration = Duration::from_secs(10);
+/// Attempts for a single request before giving up, including the first.
+const RETRY_MAX_ATTEMPTS: u32 = 5;
+
+/// Selects how harvested records are persisted. Chosen once at startup
+/// from the `--format` flag, falling back to the `OUTPUT_FORMAT` env var
+/// and then to JSON, since CSV is easier to load into analysis tooling
+/// but JSON is the safer default for arbitrary nested records.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Json,
+    Csv,
+}
+
+impl OutputFormat {
+    fn from_env(args: &[String]) -> Self {
+        let raw = flag_value(args, "--format").or_else(|| std::env::var("OUTPUT_FORMAT").ok());
+        match raw {
+            Some(ref s) if s.eq_ignore_ascii_case("csv") => OutputFormat::Csv,
+            _ => OutputFormat::Json,
+        }
+    }
+}
+
+/// Runtime knobs for the long-running harvest loop: how long to sleep
+/// between batches and when to stop. Replaces the old hard-coded
+/// `count == 5` / 5-second-sleep demo script.
+#[derive(Clone, Copy, Debug)]
+struct RunConfig {
+    interval: Duration,
+    /// Stop once this many records have actually been persisted (batches
+    /// that only turn up duplicates don't count). 0 means run forever.
+    max_records: u32,
+}
+
+impl RunConfig {
+    fn from_env(args: &[String]) -> Self {
+        let interval_secs = flag_value(args, "--interval")
+            .or_else(|| std::env::var("INTERVAL_SECONDS").ok())
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(5);
+        let max_records = flag_value(args, "--max-records")
+            .or_else(|| std::env::var("MAX_RECORDS").ok())
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+        RunConfig {
+            interval: Duration::from_secs(interval_secs),
+            max_records,
+        }
+    }
+}
+
+/// A single pluggable data source: knows where to fetch from and how to
+/// turn the response body into its typed record.
+trait Collector {
+    type Record: Serialize + DeserializeOwned + CsvRow + Identity;
+
+    /// Short, stable name used for logging and the on-disk layout.
+    fn name(&self) -> &str;
+
+    /// Fully-qualified URI to fetch for one record.
+    fn uri(&self) -> String;
+
+    /// Parse the raw response body into this collector's record type.
+    fn parse(&self, body: &str) -> Result<Self::Record, CollectError> {
+        Ok(serde_json::from_str(body)?)
+    }
+}
+
+struct CatFactCollector;
+
+impl Collector for CatFactCollector {
+    type Record = CatFact;
+
+    fn name(&self) -> &str {
+        "cat-fact"
+    }
+
+    fn uri(&self) -> String {
+        "https://cat-fact.herokuapp.com/facts/random".to_string()
+    }
+}
+
+struct WeatherCollector {
+    city: String,
+    api_key: String,
+}
+
+impl WeatherCollector {
+    /// Builds a `WeatherCollector` from `--weather-city`/`WEATHER_CITY` and
+    /// the `OPENWEATHERMAP_API_KEY` env var, or returns `None` if either is
+    /// unset, so the weather collector can be added without touching the
+    /// main loop.
+    fn from_env(args: &[String]) -> Option<Self> {
+        let city = flag_value(args, "--weather-city").or_else(|| std::env::var("WEATHER_CITY").ok())?;
+        match std::env::var("OPENWEATHERMAP_API_KEY") {
+            Ok(api_key) => Some(WeatherCollector { city, api_key }),
+            Err(_) => {
+                warn!("--weather-city/WEATHER_CITY set but OPENWEATHERMAP_API_KEY is missing, skipping weather collector");
+                None
+            }
+        }
+    }
+}
+
+impl Collector for WeatherCollector {
+    type Record = Weather;
+
+    fn name(&self) -> &str {
+        "weather"
+    }
+
+    fn uri(&self) -> String {
+        format!(
+            "https://api.openweathermap.org/data/2.5/weather?q={}&appid={}",
+            self.city, self.api_key
+        )
+    }
+}
+
+/// Enum over the collectors the main loop knows how to drive, since
+/// `Collector::Record` is an associated type and so `dyn Collector` isn't
+/// object-safe.
+enum AnyCollector {
+    CatFact(CatFactCollector),
+    Weather(WeatherCollector),
+}
+
+impl AnyCollector {
+    fn name(&self) -> &str {
+        match self {
+            AnyCollector::CatFact(c) => c.name(),
+            AnyCollector::Weather(c) => c.name(),
+        }
+    }
+
+    /// Harvests one record and returns `true` if it was new (and so was
+    /// persisted), or `false` if it was a duplicate the `SeenIndex`
+    /// already knew about.
+    fn harvest_into(
+        &self,
+        client: &reqwest::blocking::Client,
+        sink: &RecordSink,
+        seen: &mut SeenIndex,
+    ) -> Result<bool, CollectError> {
+        match self {
+            AnyCollector::CatFact(c) => harvest(client, sink, c, seen),
+            AnyCollector::Weather(c) => harvest(client, sink, c, seen),
+        }
+    }
+}
+
+/// Builds the collectors the main loop drives: cat-fact is always on, and
+/// weather is added on top of it when `WeatherCollector::from_env` finds
+/// it configured. New collectors plug in here without the main loop
+/// itself needing to change.
+fn build_collectors(args: &[String]) -> Vec<AnyCollector> {
+    let mut collectors = vec![AnyCollector::CatFact(CatFactCollector)];
+    if let Some(weather) = WeatherCollector::from_env(args) {
+        collectors.push(AnyCollector::Weather(weather));
+    }
+    collectors
+}
+
+/// What to do with a response status: succeed, retry, or fail immediately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatusOutcome {
+    Success,
+    Retry,
+    FailFast,
+}
+
+/// Classifies a response status for the retry loop: 4xx is never worth
+/// retrying, 5xx is retried up to `RETRY_MAX_ATTEMPTS`, anything else
+/// succeeds. Pure so it's unit-testable without a real server.
+fn classify_status(status: reqwest::StatusCode) -> StatusOutcome {
+    if status.is_client_error() {
+        StatusOutcome::FailFast
+    } else if status.is_server_error() {
+        StatusOutcome::Retry
+    } else {
+        StatusOutcome::Success
+    }
+}
+
+/// Fetches `uri`, retrying 5xx responses and connection errors with
+/// exponential backoff (capped at `RETRY_MAX`, up to `RETRY_MAX_ATTEMPTS`
+/// total attempts). A 4xx is returned immediately since retrying it won't
+/// help.
+fn fetch_with_retry(
+    client: &reqwest::blocking::Client,
+    uri: &str,
+) -> Result<String, CollectError> {
+    let mut attempt = 0u32;
+    loop {
+        attempt += 1;
+        match client.get(uri).send() {
+            Ok(response) => {
+                let status = response.status();
+                match classify_status(status) {
+                    StatusOutcome::Success => return Ok(response.text()?),
+                    StatusOutcome::FailFast => return Err(CollectError::HttpStatus(status)),
+                    StatusOutcome::Retry if attempt >= RETRY_MAX_ATTEMPTS => {
+                        return Err(CollectError::HttpStatus(status))
+                    }
+                    StatusOutcome::Retry => {}
+                }
+            }
+            Err(err) => {
+                if attempt >= RETRY_MAX_ATTEMPTS {
+                    return Err(CollectError::Request(err));
+                }
+            }
+        }
+        let backoff = (RETRY_BASE * 2u32.pow(attempt - 1)).min(RETRY_MAX);
+        warn!(
+            "attempt {} of {} failed, retrying in {:?}",
+            attempt, RETRY_MAX_ATTEMPTS, backoff
+        );
+        thread::sleep(backoff);
+    }
+}
+
+#[cfg(test)]
+mod retry_tests {
+    use super::*;
+
+    #[test]
+    fn client_error_fails_fast() {
+        assert_eq!(
+            classify_status(reqwest::StatusCode::BAD_REQUEST),
+            StatusOutcome::FailFast
+        );
+        assert_eq!(
+            classify_status(reqwest::StatusCode::NOT_FOUND),
+            StatusOutcome::FailFast
+        );
+    }
+
+    #[test]
+    fn server_error_retries() {
+        assert_eq!(
+            classify_status(reqwest::StatusCode::INTERNAL_SERVER_ERROR),
+            StatusOutcome::Retry
+        );
+        assert_eq!(
+            classify_status(reqwest::StatusCode::BAD_GATEWAY),
+            StatusOutcome::Retry
+        );
+    }
+
+    #[test]
+    fn success_status_succeeds() {
+        assert_eq!(classify_status(reqwest::StatusCode::OK), StatusOutcome::Success);
+    }
+}
+
+/// Where harvested records for one source land: a timestamped JSON
+/// snapshot directory or an appended CSV file, depending on the
+/// configured `OutputFormat`. One `RecordSink` should be opened per run
+/// (e.g. once per batch, per source) and reused for every record that run
+/// produces, mirroring the contract `Archive` itself documents, instead
+/// of reopening a fresh sink per record.
+enum RecordSink {
+    Json(Archive),
+    Csv(CsvSink),
+}
+
+impl RecordSink {
+    /// Opens the sink for `source` under `root` for the given `format`.
+    fn open(root: &std::path::Path, format: OutputFormat, source: &str) -> Result<Self, CollectError> {
+        Ok(match format {
+            OutputFormat::Json => RecordSink::Json(Archive::new(root, source)?),
+            OutputFormat::Csv => RecordSink::Csv(CsvSink::new(root, source)?),
+        })
+    }
+
+    fn write<R: Serialize + CsvRow>(&self, record: &R) -> Result<(), CollectError> {
+        match self {
+            RecordSink::Json(archive) => archive.write(record),
+            RecordSink::Csv(sink) => sink.append(record),
+        }
+    }
+}
+
+/// Fetch one record from `collector`, skip it if `seen` already knows its
+/// identity, and otherwise persist it through `sink`. Returns whether the
+/// record was new.
+fn harvest<C: Collector>(
+    client: &reqwest::blocking::Client,
+    sink: &RecordSink,
+    collector: &C,
+    seen: &mut SeenIndex,
+) -> Result<bool, CollectError> {
+    let body = fetch_with_retry(client, &collector.uri())?;
+    let record: C::Record = collector.parse(&body)?;
+    let identity = record.identity();
+    if seen.contains(&identity) {
+        info!(
+            "[{}] Skipping duplicate record (id={})",
+            collector.name(),
+            identity
+        );
+        return Ok(false);
+    }
+    // Persist before marking the identity seen: if persisting fails, the
+    // next run should still retry this record instead of silently
+    // treating it as already stored.
+    sink.write(&record)?;
+    info!("[{}] Persisted one record", collector.name());
+    seen.insert(&identity)?;
+    Ok(true)
+}
+
+/// Reads a JSON array of `CatFact` records from `path`, or from stdin
+/// when `path` is `None`, and persists each one without hitting the
+/// network. Lets users replay previously exported data, seed a fresh
+/// store, or migrate records between machines.
+fn run_import(path: Option<&str>, root: &std::path::Path, format: OutputFormat) -> Result<()> {
+    let body = match path {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => {
+            use std::io::Read;
+            let mut body = String::new();
+            std::io::stdin().read_to_string(&mut body)?;
+            body
+        }
+    };
+    let records: Vec<CatFact> = serde_json::from_str(&body)?;
+    let count = records.len();
+    // One sink for the whole import, not one per record, so an imported
+    // batch lands in a single run directory (or a single CSV file)
+    // instead of scattering into one directory per record.
+    let sink = RecordSink::open(root, format, "cat-fact")?;
+    for record in &records {
+        sink.write(record)?;
+    }
+    info!("Imported {} records", count);
+    Ok(())
+}
+
+fn print_help() {
+    println!(
+        "playground-data-collection-rust\n\n\
+         USAGE:\n    \
+         playground-data-collection-rust [--format <json|csv>] [--interval <secs>] [--max-records <n>] [--weather-city <city>]\n    \
+         playground-data-collection-rust import [FILE] [--format <json|csv>]\n\n\
+         With no subcommand, continuously harvests records from the configured\n\
+         collectors every `--interval` seconds (default 5) until `--max-records`\n\
+         records have actually been persisted (default 0, meaning run forever;\n\
+         duplicates skipped by dedup don't count) or SIGINT is received.\n\
+         The cat-fact collector always runs; passing `--weather-city <city>`\n\
+         (or setting `WEATHER_CITY`) adds the weather collector too, provided\n\
+         `OPENWEATHERMAP_API_KEY` is also set.\n\
+         `import` reads a JSON array of records from FILE, or from stdin when FILE\n\
+         is omitted, and persists each one without querying the remote API."
+    );
+}
 
 fn main() -> Result<(), anyhow::Error> {
     env_logger::init();
-    info!("Starting up");
-    let mut count = 0u32;
+
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        print_help();
+        return Ok(());
+    }
+
+    let root = std::path::Path::new("data");
+    let format = OutputFormat::from_env(&args);
+
+    if args.get(1).map(String::as_str) == Some("import") {
+        let path = args.get(2).filter(|a| !a.starts_with("--"));
+        return run_import(path.map(String::as_str), root, format);
+    }
+
+    let config = RunConfig::from_env(&args);
+    info!(
+        "Starting up (interval={:?}, max_records={})",
+        config.interval, config.max_records
+    );
     let client = reqwest::blocking::Client::new();
-    let uri = "https://cat-fact.herokuapp.com/facts/random";
-    let db: Store = Store::new("data")?;
+
+    let collectors = build_collectors(&args);
+
+    let mut seen_indexes: HashMap<String, SeenIndex> = HashMap::new();
+    for collector in &collectors {
+        seen_indexes.insert(collector.name().to_string(), SeenIndex::load(root, collector.name())?);
+    }
+
+    let shutdown = Arc::new(AtomicBool::new(false));
+    {
+        let shutdown = shutdown.clone();
+        ctrlc::set_handler(move || {
+            warn!("Received interrupt, finishing the current batch before exiting");
+            shutdown.store(true, Ordering::SeqCst);
+        })?;
+    }
+
+    let mut batch = 0u32;
+    let mut persisted_total = 0u32;
     loop {
-        count += 1;
-        let response = client.get(uri).send()?;
-        if response.status().is_client_error() || response.status().is_server_error() {
-            return Err(anyhow!("Server responded with: {}", response.status()));
-        }
-        let string: CatFact = serde_json::from_str(&response.text()?)?;
-        let key = db.save(&string)?;
-        info!("Written one file with key: {}", key);
-        thread::sleep(Duration::from_millis(5000));
-        if count == 5 {
+        batch += 1;
+        let (mut new, mut skipped) = (0u32, 0u32);
+        for collector in &collectors {
+            let index = seen_indexes
+                .get_mut(collector.name())
+                .expect("every collector has a SeenIndex loaded above");
+            // Open one sink per collector per batch so every record this
+            // collector produces this batch shares a single run (see
+            // `RecordSink`), rather than opening a fresh one on every
+            // record.
+            //
+            // A single collector's failure (exhausted retries, a 4xx, a
+            // disk error) is logged and skipped rather than propagated, so
+            // one flaky source doesn't take down an otherwise healthy
+            // unattended daemon; it just gets retried next interval.
+            let outcome = RecordSink::open(root, format, collector.name())
+                .and_then(|sink| collector.harvest_into(&client, &sink, index));
+            match outcome {
+                Ok(true) => new += 1,
+                Ok(false) => skipped += 1,
+                Err(err) => error!(
+                    "[{}] Harvest failed, will retry next interval: {}",
+                    collector.name(),
+                    err
+                ),
+            }
+        }
+        persisted_total += new;
+        info!(
+            "Batch {}: {} new, {} duplicate(s) skipped ({} persisted total)",
+            batch, new, skipped, persisted_total
+        );
+
+        if shutdown.load(Ordering::SeqCst) {
+            info!("Shutdown requested, exiting");
+            break;
+        }
+        if config.max_records != 0 && persisted_total >= config.max_records {
+            info!(
+                "Reached max-records ({}), exiting",
+                config.max_records
+            );
+            break;
+        }
+
+        let next_wake = SystemTime::now() + config.interval;
+        info!(
+            "Sleeping {:?}, next wake at ~{}s since epoch",
+            config.interval,
+            next_wake
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        );
+        thread::sleep(config.interval);
+        if shutdown.load(Ordering::SeqCst) {
+            info!("Shutdown requested, exiting");
             break;
-        } else {
-            continue;
         }
     }
     Ok(())
@@ -52,4 +515,89 @@ struct CatFact {
 struct Status {
     verified: bool,
     sentCount: i32
-}
\ No newline at end of file
+}
+
+impl Identity for CatFact {
+    fn identity(&self) -> String {
+        self._id.clone()
+    }
+}
+
+impl CsvRow for CatFact {
+    fn headers() -> Vec<&'static str> {
+        vec![
+            "used",
+            "source",
+            "type",
+            "deleted",
+            "_id",
+            "__v",
+            "text",
+            "updatedAt",
+            "createdAt",
+            "status.verified",
+            "status.sentCount",
+            "user",
+        ]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.used.to_string(),
+            self.source.clone(),
+            self.r#type.clone(),
+            self.deleted.to_string(),
+            self._id.clone(),
+            self.__v.to_string(),
+            self.text.clone(),
+            self.updatedAt.clone(),
+            self.createdAt.clone(),
+            self.status.verified.to_string(),
+            self.status.sentCount.to_string(),
+            self.user.clone(),
+        ]
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Weather {
+    name: String,
+    /// Unix timestamp (seconds) of when the API calculated this reading.
+    dt: i64,
+    main: WeatherMain,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WeatherMain {
+    temp: f64,
+    pressure: i32,
+    humidity: i32,
+}
+
+impl Identity for Weather {
+    // The OpenWeatherMap current-conditions response has no record id of
+    // its own. The city name alone isn't a logical key either — weather
+    // is exactly the time-series data we want a fresh reading of on
+    // every harvest — so pair it with the reading's own timestamp
+    // instead, which only collides when the API returns the same
+    // observation twice.
+    fn identity(&self) -> String {
+        format!("{}@{}", self.name, self.dt)
+    }
+}
+
+impl CsvRow for Weather {
+    fn headers() -> Vec<&'static str> {
+        vec!["name", "dt", "main.temp", "main.pressure", "main.humidity"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.dt.to_string(),
+            self.main.temp.to_string(),
+            self.main.pressure.to_string(),
+            self.main.humidity.to_string(),
+        ]
+    }
+}